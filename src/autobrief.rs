@@ -0,0 +1,106 @@
+/// Abbreviations which end with a period without ending the sentence.
+const ABBREVIATIONS: [&str; 8] = ["e.g", "i.e", "cf", "vs", "approx", "al", "resp", "incl"];
+
+/// Closing punctuation which belongs to the sentence before it, like `。」`.
+const CLOSING: [char; 8] = ['"', '\'', ')', ']', '」', '』', '）', '”'];
+
+/// Finds the end of the first sentence of `text`, as a byte index.
+///
+/// This is the default sentence splitter of [`Transformer::autobrief`](crate::Transformer). A
+/// sentence ends at `.`, `!` or `?` followed by whitespace (so `1.5` or `std::io` don't split it),
+/// unless the period is part of an abbreviation like `e.g.` or `i.e.`, or right after a full-width
+/// terminator like `。`, `！` or `？`, which are used without spaces. Inline code, like
+/// `` `a. b` ``, never ends a sentence.
+///
+/// # Examples
+///
+/// ```
+/// use doxygen_rs::first_sentence_end;
+///
+/// assert_eq!(first_sentence_end("Frees it, e.g. on exit. Then returns."), Some(23));
+/// assert_eq!(first_sentence_end("释放内存。然后返回。"), Some(15));
+/// assert_eq!(first_sentence_end("No sentence end"), None);
+/// ```
+pub fn first_sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        let ends = match c {
+            '`' => {
+                let run = backtick_run(&text[index..]);
+                let skip_to = closing_backticks(&text[index + run..], run)
+                    .map_or(index + run, |end| index + run + end);
+                while chars.next_if(|(index, _)| *index < skip_to).is_some() {}
+                false
+            }
+            '。' | '！' | '？' | '．' => true,
+            '.' | '!' | '?' => {
+                let followed_by_space = chars.peek().is_none_or(|(_, v)| v.is_whitespace());
+                let word = text[..index]
+                    .rsplit(char::is_whitespace)
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches(|v: char| !v.is_alphanumeric())
+                    .to_lowercase();
+                followed_by_space && !(c == '.' && ABBREVIATIONS.contains(&word.as_str()))
+            }
+            _ => false,
+        };
+
+        if ends {
+            let mut end = index + c.len_utf8();
+            while let Some((index, c)) = chars.next_if(|(_, v)| CLOSING.contains(v)) {
+                end = index + c.len_utf8();
+            }
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+pub(crate) fn backtick_run(text: &str) -> usize {
+    text.len() - text.trim_start_matches('`').len()
+}
+
+/// Finds the end of the run of exactly `run` backticks closing an inline code span.
+pub(crate) fn closing_backticks(text: &str, run: usize) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('`') {
+        let start = offset + start;
+        let len = backtick_run(&text[start..]);
+        if len == run {
+            return Some(start + len);
+        }
+        offset = start + len;
+    }
+
+    None
+}
+
+/// Splits the first sentence of the first paragraph into a paragraph of its own, so Rustdoc uses
+/// it as the summary of the item.
+pub(crate) fn autobrief(text: String, split: impl Fn(&str) -> Option<usize>) -> String {
+    // Headings, quotes, lists and fences end a paragraph even without a blank line
+    let paragraph_end = text
+        .match_indices('\n')
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let line = &text[index + 1..];
+            line.starts_with(['\n', '#', '>'])
+                || ["* ", "- ", "```"].iter().any(|v| line.starts_with(v))
+        })
+        .unwrap_or(text.len());
+    let paragraph = &text[..paragraph_end];
+
+    if paragraph.starts_with(['#', '>', '*', '-', '|', '`']) {
+        return text;
+    }
+
+    match split(paragraph) {
+        Some(end) if paragraph.is_char_boundary(end) && !paragraph[end..].trim().is_empty() => {
+            format!("{}\n\n{}", text[..end].trim_end(), text[end..].trim_start())
+        }
+        _ => text,
+    }
+}
@@ -0,0 +1,115 @@
+use crate::parser::GrammarItem;
+use std::ops::Range;
+
+/// Renders the parsed items of a comment as a Graphviz DOT graph.
+///
+/// Every item is a node labeled with its span in `input`, as a byte range and lines, items between
+/// `@{` and `@}` are nested under their group.
+pub(crate) fn ast_dot(input: &str, items: &[(GrammarItem, Range<usize>)]) -> String {
+    let mut result = String::from("digraph ast {\n    node [shape=box, fontname=monospace];\n");
+    result += "    n0 [label=\"Comment\"];\n";
+    let mut parents = vec![0];
+
+    for (index, (item, span)) in items.iter().enumerate() {
+        let id = index + 1;
+        let start = input[..span.start].matches('\n').count() + 1;
+        let end = start
+            + input[span.clone()]
+                .trim_end_matches('\n')
+                .matches('\n')
+                .count();
+        let lines = if start == end {
+            format!("line {start}")
+        } else {
+            format!("lines {start}-{end}")
+        };
+        let position = format!("{}..{}, {lines}", span.start, span.end);
+
+        let label = match item {
+            GrammarItem::Notation { meta, params, tag } => {
+                let mut label = format!("Notation ({position})\ntag: {tag}");
+                if !meta.is_empty() {
+                    label += &format!("\nmeta: {}", meta.join(", "));
+                }
+                if !params.is_empty() {
+                    label += &format!("\nparams: {}", params.join(", "));
+                }
+                label
+            }
+            GrammarItem::Text(v) => format!("Text ({position})\n{v:?}"),
+            GrammarItem::GroupStart => format!("Group ({position})"),
+            GrammarItem::GroupEnd => format!("GroupEnd ({position})"),
+        };
+
+        if matches!(item, GrammarItem::GroupEnd) && parents.len() > 1 {
+            parents.pop();
+        }
+
+        let parent = parents.last().copied().unwrap_or_default();
+        result += &format!("    n{id} [label=\"{}\"];\n", escape(&label));
+        result += &format!("    n{parent} -> n{id};\n");
+
+        if matches!(item, GrammarItem::GroupStart) {
+            parents.push(id);
+        }
+    }
+
+    result + "}\n"
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\l")
+        + "\\l"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_spanned;
+    use crate::Transformer;
+
+    #[test]
+    fn nests_groups() {
+        let input = "@{\n* @param[in] v A \"value\"\n@}";
+        let items = parse_spanned(input, &Transformer::default()).unwrap();
+        assert_eq!(
+            ast_dot(input, &items),
+            r#"digraph ast {
+    node [shape=box, fontname=monospace];
+    n0 [label="Comment"];
+    n1 [label="Group (0..2, line 1)\l"];
+    n0 -> n1;
+    n2 [label="Text (3..5, line 2)\l\"* \"\l"];
+    n1 -> n2;
+    n3 [label="Notation (5..17, line 2)\ltag: param\lmeta: in\lparams: v\l"];
+    n1 -> n3;
+    n4 [label="Text (17..28, line 2)\l\" A \\\"value\\\"\\n\"\l"];
+    n1 -> n4;
+    n5 [label="GroupEnd (28..30, line 3)\l"];
+    n0 -> n5;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn non_ascii_spans() {
+        let input = "@note café\n释放缓冲区";
+        let items = parse_spanned(input, &Transformer::default()).unwrap();
+        assert_eq!(
+            ast_dot(input, &items),
+            r#"digraph ast {
+    node [shape=box, fontname=monospace];
+    n0 [label="Comment"];
+    n1 [label="Notation (0..5, line 1)\ltag: note\l"];
+    n0 -> n1;
+    n2 [label="Text (6..27, lines 1-2)\l\"café\\n释放缓冲区\"\l"];
+    n0 -> n2;
+}
+"#
+        );
+    }
+}
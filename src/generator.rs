@@ -1,5 +1,8 @@
+use crate::autobrief::{autobrief, backtick_run, closing_backticks, first_sentence_end};
 use crate::emojis;
+use crate::markdown::{has_commands, is_rich_markdown, is_rustdoc};
 use crate::parser::{parse, GrammarItem, ParseError};
+use crate::{ArgumentWhitespace, Command, Renderer, Transformer};
 
 /// Creates a Rustdoc string from a Doxygen string.
 ///
@@ -8,26 +11,131 @@ use crate::parser::{parse, GrammarItem, ParseError};
 /// This function can error if there are missing parts of a given Doxygen annotation (like `@param`
 /// missing the variable name)
 pub fn rustdoc(input: String) -> Result<String, ParseError> {
-    let parsed = parse(input)?;
+    generate(input, &Transformer::default())
+}
+
+pub(crate) fn generate(input: String, transformer: &Transformer) -> Result<String, ParseError> {
+    // Converting would only mangle the Markdown, like the alignment of tables, or convert already
+    // converted comments again, so only the options working on the Markdown are applied
+    if !has_commands(&input) && (is_rich_markdown(&input) || is_rustdoc(&input)) {
+        return Ok(apply_options(input, &[], transformer));
+    }
+
+    let parsed = strip_internal(parse(input, transformer)?);
     let mut result = String::new();
     let mut already_added_params = false;
     let mut already_added_returns = false;
     let mut already_added_throws = false;
+    let mut already_added_examples = false;
     let mut group_started = false;
+    let mut in_returns = false;
+    let mut pending_example = None;
+    let mut in_brief = false;
+
+    let out_params: Vec<String> = if transformer.link_out_params {
+        parsed
+            .iter()
+            .filter_map(|item| match item {
+                GrammarItem::Notation { meta, params, tag }
+                    if tag == "param" && meta.iter().any(|v| v == "out") =>
+                {
+                    params.first().cloned()
+                }
+                _ => None,
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let parsed_see_also: Vec<(String, String)> = parsed
+        .iter()
+        .filter_map(|item| match item {
+            GrammarItem::Notation { params, tag, .. }
+                if matches!(tag.as_str(), "sa" | "see" | "ref") =>
+            {
+                Some((tag.clone(), params.first()?.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut items = parsed.into_iter().peekable();
+    while let Some(item) = items.next() {
+        // Inlined examples follow their description
+        let ends_description = match &item {
+            GrammarItem::Notation { tag, .. } => !is_inline(tag),
+            GrammarItem::Text(_) => false,
+            GrammarItem::GroupStart | GrammarItem::GroupEnd => true,
+        };
+        if ends_description {
+            if let Some(block) = pending_example.take() {
+                append_example(&mut result, block);
+            }
+        }
+
+        // With autobrief, the brief description is a paragraph of its own, so splitting it again
+        // when converting the output doesn't change it
+        let breaks_paragraph = matches!(
+            &item,
+            GrammarItem::Notation { tag, .. } if matches!(tag.as_str(), "details" | "pre" | "post")
+        );
+        if transformer.autobrief && in_brief && ends_description {
+            in_brief = false;
+            let len = result.trim_end_matches('\n').len();
+            if len > 0 && !breaks_paragraph {
+                result.truncate(len);
+                result += "\n\n";
+            }
+        }
 
-    for item in parsed {
         result += &match item {
             GrammarItem::Notation { meta, params, tag } => {
-                let (str, (added_param, added_return, added_throws)) = generate_notation(
-                    tag,
-                    meta,
-                    params,
-                    (
-                        already_added_params,
-                        already_added_returns,
-                        already_added_throws,
-                    ),
-                );
+                if tag == "brief" || tag == "short" {
+                    in_brief = true;
+                }
+
+                if tag == "example" {
+                    pending_example = params.first().and_then(|file| {
+                        let resolve = transformer.example_resolver.as_ref()?;
+                        Some(example_block(file, &resolve(file)?))
+                    });
+                }
+
+                match tag.as_str() {
+                    "returns" | "return" | "result" | "retval" => in_returns = true,
+                    v if is_inline(v) => {}
+                    _ => in_returns = false,
+                }
+
+                // The whitespace after the arguments is trimmed, so separate them from the text
+                let separate = transformer.argument_whitespace == ArgumentWhitespace::Trim
+                    && !params.is_empty()
+                    && matches!(
+                        items.peek(),
+                        Some(GrammarItem::Text(v)) if v.starts_with(|c: char| !c.is_whitespace())
+                    );
+
+                let render_override = transformer.command_overrides.get(&tag);
+                let command = render_override.map(|_| Command {
+                    tag: tag.clone(),
+                    meta: meta.clone(),
+                    params: params.clone(),
+                });
+
+                let (str, (added_param, added_return, added_throws, added_example)) =
+                    generate_notation(
+                        tag,
+                        meta,
+                        params,
+                        (
+                            already_added_params,
+                            already_added_returns,
+                            already_added_throws,
+                            already_added_examples,
+                        ),
+                        transformer,
+                    );
                 if added_param {
                     already_added_params = true;
                 }
@@ -40,13 +148,50 @@ pub fn rustdoc(input: String) -> Result<String, ParseError> {
                     already_added_throws = true;
                 }
 
-                str
+                if added_example {
+                    already_added_examples = true;
+                }
+
+                // The comment doesn't start with a paragraph break, like for a leading `@details`
+                let str = if str == "\n\n" && result.is_empty() {
+                    String::new()
+                } else if separate {
+                    str + " "
+                } else {
+                    str
+                };
+
+                match (render_override, command) {
+                    (Some(render), Some(command)) => render(
+                        &command,
+                        &Renderer {
+                            transformer,
+                            tag: &command.tag,
+                            default_output: &str,
+                        },
+                    ),
+                    _ => str,
+                }
             }
             GrammarItem::Text(v) => {
-                if group_started {
+                let v = if group_started {
                     v.replacen('*', "", 1)
                 } else {
                     v
+                };
+
+                if in_returns && !out_params.is_empty() {
+                    // The description of the return value ends with its paragraph
+                    let end = match v.find("\n\n") {
+                        Some(end) => {
+                            in_returns = false;
+                            end
+                        }
+                        None => v.len(),
+                    };
+                    link_out_params(&v[..end], &out_params) + &v[end..]
+                } else {
+                    v
                 }
             }
             // See <https://stackoverflow.com/a/40354789>
@@ -61,23 +206,347 @@ pub fn rustdoc(input: String) -> Result<String, ParseError> {
         };
     }
 
-    Ok(result)
+    if let Some(block) = pending_example {
+        append_example(&mut result, block);
+        result.truncate(result.trim_end().len());
+    }
+
+    // Leading whitespace would be dropped when converting the output again
+    let result = result.trim_start().to_string();
+    Ok(apply_options(result, &parsed_see_also, transformer))
+}
+
+/// Applies the options of `transformer` working on the generated Markdown as a whole.
+fn apply_options(
+    mut result: String,
+    see_also: &[(String, String)],
+    transformer: &Transformer,
+) -> String {
+    if transformer.autobrief {
+        result = match &transformer.sentence_splitter {
+            Some(split) => autobrief(result, |v| split(v)),
+            None => autobrief(result, first_sentence_end),
+        };
+    }
+
+    if transformer.aggregate_see_also {
+        result = append_see_also(result, see_also, transformer);
+    }
+
+    if let Some(depth) = transformer.max_heading_depth {
+        result = clamp_headings(&result, depth);
+    }
+
+    result
+}
+
+/// Appends a `# See also` section with the targets of `@sa`/`@see`, without duplicates and without
+/// targets already linked with `@ref`.
+fn append_see_also(
+    result: String,
+    references: &[(String, String)],
+    transformer: &Transformer,
+) -> String {
+    let mut targets: Vec<&str> = vec![];
+    for (tag, target) in references {
+        if tag != "ref"
+            && !targets.contains(&target.as_str())
+            && !references
+                .iter()
+                .any(|(tag, v)| tag == "ref" && v == target)
+        {
+            targets.push(target);
+        }
+    }
+
+    if targets.is_empty() {
+        return result;
+    }
+
+    if let Some(order) = &transformer.see_also_order {
+        targets.sort_by(|a, b| order(a, b));
+    }
+
+    let mut result = result.trim_end().to_string();
+    if !result.is_empty() {
+        result += "\n\n";
+    }
+
+    result += "# See also\n\n";
+    result += &targets
+        .iter()
+        .map(|v| format!("* [`{v}`]"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    result
+}
+
+/// Renders Markdown and HTML headings deeper than `max_depth` as bold text, leaving code blocks
+/// untouched.
+fn clamp_headings(text: &str, max_depth: usize) -> String {
+    let mut in_code_block = false;
+
+    text.split('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code_block = !in_code_block;
+            }
+            if in_code_block {
+                return line.to_string();
+            }
+
+            let depth = trimmed.len() - trimmed.trim_start_matches('#').len();
+            let title = &trimmed[depth..];
+            let line = if depth > max_depth
+                && depth <= 6
+                && (title.is_empty() || title.starts_with(' '))
+            {
+                format!("**{}**", title.trim().trim_end_matches('#').trim_end())
+            } else {
+                line.to_string()
+            };
+
+            clamp_html_headings(&line, max_depth)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders the HTML headings deeper than `max_depth` in the line as bold text, leaving inline code
+/// and headings without a closing tag untouched.
+fn clamp_html_headings(line: &str, max_depth: usize) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(['<', '`']) {
+        result += &rest[..start];
+        rest = &rest[start..];
+
+        let len = if rest.starts_with('`') {
+            let run = backtick_run(rest);
+            closing_backticks(&rest[run..], run).map_or(run, |end| run + end)
+        } else if let Some((title, len)) = html_heading(rest, max_depth) {
+            result += &format!("**{title}**");
+            rest = &rest[len..];
+            continue;
+        } else {
+            1
+        };
+
+        result += &rest[..len];
+        rest = &rest[len..];
+    }
+
+    result + rest
+}
+
+/// Parses an HTML heading deeper than `max_depth` at the start of `text`, returning its title and
+/// the length of the heading up to its closing tag.
+fn html_heading(text: &str, max_depth: usize) -> Option<(&str, usize)> {
+    let depth = text.strip_prefix("<h")?.chars().next()?.to_digit(10)? as usize;
+    if depth <= max_depth || depth > 6 {
+        return None;
+    }
+
+    let attributes = &text[3..];
+    let open_end = attributes.find('>')?;
+    if open_end > 0 && !attributes.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    // The closing tag can't be inside inline code
+    let title_start = 3 + open_end + 1;
+    let close = format!("</h{depth}>");
+    let mut title_end = title_start;
+    while !text[title_end..].starts_with(&close) {
+        title_end += match text[title_end..].chars().next()? {
+            '`' => {
+                let run = backtick_run(&text[title_end..]);
+                closing_backticks(&text[title_end + run..], run).map_or(run, |end| run + end)
+            }
+            c => c.len_utf8(),
+        };
+    }
+
+    Some((&text[title_start..title_end], title_end + close.len()))
+}
+
+/// Renders the content of an example file as a code block, using a fence longer than any run of
+/// backticks in the content.
+fn example_block(file: &str, content: &str) -> String {
+    let language = match file.rsplit_once('.').map(|(_, v)| v) {
+        Some("c" | "h") => "c",
+        Some("cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx") => "cpp",
+        _ => "text",
+    };
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    format!("{fence}{language}\n{}\n{fence}", content.trim_end())
+}
+
+fn append_example(result: &mut String, block: String) {
+    result.truncate(result.trim_end().len());
+    *result += "\n\n";
+    *result += &block;
+    *result += "\n";
+}
+
+/// Removes the parts of the comment hidden by `@internal`.
+pub(crate) fn strip_internal(parsed: Vec<GrammarItem>) -> Vec<GrammarItem> {
+    let last_endinternal = parsed.iter().rposition(
+        |item| matches!(item, GrammarItem::Notation { tag, .. } if tag == "endinternal"),
+    );
+    let mut result: Vec<GrammarItem> = Vec::with_capacity(parsed.len());
+    let mut internal = None;
+
+    for (index, mut item) in parsed.into_iter().enumerate() {
+        if let Some(scope) = internal {
+            match (&item, scope) {
+                (GrammarItem::Notation { tag, .. }, _) if tag == "endinternal" => {
+                    internal = None;
+                    continue;
+                }
+                (GrammarItem::Text(v), InternalScope::Paragraph) => match v.find("\n\n") {
+                    Some(end) => {
+                        // Keep the paragraph break, unless the visible text already ends a line
+                        // or there's no visible text before it
+                        let visible = result
+                            .iter()
+                            .any(|v| !matches!(v, GrammarItem::Text(v) if v.trim().is_empty()));
+                        let ends_line = matches!(
+                            result.last(),
+                            Some(GrammarItem::Text(v)) if v.ends_with('\n')
+                        );
+                        let start = match (visible, ends_line) {
+                            (false, _) => end + 2,
+                            (true, true) => end + 1,
+                            (true, false) => end,
+                        };
+                        item = GrammarItem::Text(v[start..].into());
+                        internal = None;
+                    }
+                    None => continue,
+                },
+                (GrammarItem::Notation { tag, .. }, InternalScope::Paragraph)
+                    if !is_inline(tag) =>
+                {
+                    internal = None;
+                }
+                _ => continue,
+            }
+        }
+
+        if let GrammarItem::Notation { tag, .. } = &item {
+            if tag == "internal" {
+                internal = if last_endinternal.is_some_and(|end| end > index) {
+                    Some(InternalScope::Block)
+                } else {
+                    Some(InternalScope::Paragraph)
+                };
+                continue;
+            }
+        }
+
+        result.push(item);
+    }
+
+    result
+}
+
+/// The part of the comment hidden by `@internal`.
+#[derive(Debug, Clone, Copy)]
+enum InternalScope {
+    /// Until the matching `@endinternal`.
+    Block,
+    /// Until the end of the current paragraph or section, when there's no `@endinternal`.
+    Paragraph,
+}
+
+/// Returns whether the notation is styling text in place instead of starting a new section.
+pub(crate) fn is_inline(tag: &str) -> bool {
+    matches!(
+        tag,
+        "a" | "b" | "c" | "p" | "e" | "em" | "emoji" | "sa" | "see" | "ref"
+    )
+}
+
+/// Replaces dereferenced mentions of the given `[out]` parameters (like `*out` or `&out`) with a
+/// link to the `# Arguments` section. Bare words are left alone, as parameter names are often
+/// regular words too.
+fn link_out_params(text: &str, out_params: &[String]) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(|c: char| is_ident(c) || c == '*' || c == '&') {
+        let (before, candidate) = rest.split_at(start);
+        result += before;
+
+        let prefix_len = candidate.len() - candidate.trim_start_matches(['*', '&']).len();
+        let word_len = candidate[prefix_len..]
+            .find(|c: char| !is_ident(c))
+            .unwrap_or(candidate.len() - prefix_len);
+        let mention = &candidate[..prefix_len + word_len];
+        let preceded_by_ident = result.chars().next_back().is_some_and(is_ident);
+
+        if prefix_len > 0
+            && word_len > 0
+            && !preceded_by_ident
+            && out_params.iter().any(|v| v == &mention[prefix_len..])
+        {
+            result += &format!("[`{mention}`](#arguments)");
+        } else {
+            result += mention;
+        }
+
+        rest = &candidate[mention.len()..];
+    }
+
+    result + rest
+}
+
+fn xref(label: &str, attributes: &[String]) -> String {
+    if attributes.is_empty() {
+        format!("> **{label}:** ")
+    } else {
+        format!("> **{label}** ({}): ", xref_attributes(attributes))
+    }
+}
+
+/// Renders attributes like `owner=alice` as prose (`owner: alice`).
+fn xref_attributes(attributes: &[String]) -> String {
+    attributes
+        .iter()
+        .map(|v| match v.split_once('=') {
+            Some((key, value)) => format!("{}: {}", key.trim(), value.trim()),
+            None => v.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 fn generate_notation(
     tag: String,
     meta: Vec<String>,
     params: Vec<String>,
-    (already_params, already_returns, already_throws): (bool, bool, bool),
-) -> (String, (bool, bool, bool)) {
+    (already_params, already_returns, already_throws, already_examples): (bool, bool, bool, bool),
+    transformer: &Transformer,
+) -> (String, (bool, bool, bool, bool)) {
     let mut new_param = false;
     let mut new_return = false;
     let mut new_throw = false;
+    let mut new_example = false;
 
     (
         match tag.as_str() {
             "param" => {
-                let param = params.get(0);
+                let param = params.first();
                 new_param = true;
                 let mut str = if !already_params {
                     "# Arguments\n\n".into()
@@ -92,11 +561,11 @@ fn generate_notation(
                         format!(
                             "* `{}` (direction {}, {}) -",
                             param,
-                            meta.get(0).unwrap(),
+                            meta.first().unwrap(),
                             second
                         )
                     } else {
-                        format!("* `{}` (direction {}) -", param, meta.get(0).unwrap())
+                        format!("* `{}` (direction {}) -", param, meta.first().unwrap())
                     }
                 } else {
                     String::new()
@@ -106,33 +575,38 @@ fn generate_notation(
             }
             "a" | "e" | "em" => {
                 let word = params
-                    .get(0)
+                    .first()
                     .expect("@a/@e/@em doesn't contain a word to style");
                 format!("_{word}_")
             }
             "b" => {
-                let word = params.get(0).expect("@b doesn't contain a word to style");
+                let word = params.first().expect("@b doesn't contain a word to style");
                 format!("**{word}**")
             }
             "c" | "p" => {
                 let word = params
-                    .get(0)
+                    .first()
                     .expect("@c/@p doesn't contain a word to style");
                 format!("`{word}`")
             }
             "emoji" => {
-                let word = params.get(0).expect("@emoji doesn't contain an emoji");
+                let word = params.first().expect("@emoji doesn't contain an emoji");
                 emojis::EMOJIS
                     .get(&word.replace(':', ""))
                     .expect("invalid emoji")
                     .to_string()
             }
-            "sa" | "see" => {
-                let code_ref = params.get(0).expect("@sa/@see doesn't contain a reference");
+            "sa" | "see" if transformer.aggregate_see_also => String::new(),
+            // Without a target, there's nothing to link
+            "ref" if params.is_empty() => String::new(),
+            "sa" | "see" | "ref" => {
+                let code_ref = params
+                    .first()
+                    .expect("@sa/@see/@ref doesn't contain a reference");
                 format!("[`{code_ref}`]")
             }
             "retval" => {
-                let var = params.get(0).expect("@retval doesn't contain a parameter");
+                let var = params.first().expect("@retval doesn't contain a parameter");
                 new_return = true;
                 let mut str = if !already_returns {
                     "# Returns\n\n".into()
@@ -153,7 +627,7 @@ fn generate_notation(
             }
             "throw" | "throws" | "exception" => {
                 new_throw = true;
-                let exception = params.get(0).expect("@param doesn't contain a parameter");
+                let exception = params.first().expect("@param doesn't contain a parameter");
 
                 let mut str = if !already_throws {
                     "# Throws\n\n".into()
@@ -164,16 +638,39 @@ fn generate_notation(
                 str += &format!("* [`{exception}`] -");
                 str
             }
+            // Without a file name, there's nothing to list
+            "example" if params.is_empty() => String::new(),
+            "example" => {
+                new_example = true;
+                let file = &params[0];
+
+                let mut str = if !already_examples {
+                    "# Examples\n\n".into()
+                } else {
+                    String::new()
+                };
+
+                str += &format!("* `{file}` -");
+                str
+            }
             "note" => String::from("> **Note:** "),
             "since" => String::from("> Available since: "),
-            "deprecated" => String::from("> **Deprecated** "),
+            "todo" => xref("Todo", &meta),
+            "bug" => xref("Bug", &meta),
+            "test" => xref("Test", &meta),
+            "deprecated" if meta.is_empty() => String::from("> **Deprecated** "),
+            "deprecated" => format!("> **Deprecated** ({}) ", xref_attributes(&meta)),
             "remark" | "remarks" => String::from("> "),
             "par" => String::from("# "),
+            "section" => String::from("#"),
+            "subsection" => String::from("##"),
+            "subsubsection" => String::from("###"),
+            "paragraph" => String::from("####"),
             "details" | "pre" | "post" => String::from("\n\n"),
             "brief" | "short" => String::new(),
             _ => String::new(),
         },
-        (new_param, new_return, new_throw),
+        (new_param, new_return, new_throw, new_example),
     )
 }
 
@@ -291,6 +788,8 @@ mod test {
             "@brief This function is insane!\n@details This is an insane function because its functionality and performance is quite astonishing.",
             "This function is insane!\n\n\nThis is an insane function because its functionality and performance is quite astonishing."
         );
+
+        test_rustdoc!("@pre  x > 0\n@post y", "x > 0\n\n\ny");
     }
 
     #[test]
@@ -359,6 +858,386 @@ mod test {
         );
     }
 
+    #[test]
+    fn links_out_params_in_returns() {
+        let transformer = Transformer::new().link_out_params(true);
+        let result = transformer
+            .transform("@param[out] out The handle.\n@param[in] size The size.\n@returns Zero on success, the handle is returned in *out and not in out. The size is @c size and not outer.")
+            .unwrap();
+        assert_eq!(
+            result,
+            "# Arguments\n\n* `out` (direction out) - The handle.\n* `size` (direction in) - The size.\n# Returns\n\nZero on success, the handle is returned in [`*out`](#arguments) and not in out. The size is `size` and not outer."
+        );
+
+        let result = Transformer::new()
+            .transform("@param[out] out The handle.\n@returns The handle in *out.")
+            .unwrap();
+        assert_eq!(
+            result,
+            "# Arguments\n\n* `out` (direction out) - The handle.\n# Returns\n\nThe handle in *out."
+        );
+
+        let result = transformer
+            .transform("@param[out] h The handle.\n@return ok in *h @c now\n\nUnrelated *h.")
+            .unwrap();
+        assert_eq!(
+            result,
+            "# Arguments\n\n* `h` (direction out) - The handle.\n# Returns\n\nok in [`*h`](#arguments) `now`\n\nUnrelated *h."
+        );
+    }
+
+    #[test]
+    fn xrefs() {
+        test_rustdoc!(
+            "@todo Handle overflows.\n@bug Crashes on empty input.",
+            "> **Todo:** Handle overflows.\n> **Bug:** Crashes on empty input."
+        );
+    }
+
+    #[test]
+    fn xref_attributes() {
+        let transformer = Transformer::new().xref_attributes('(', ')');
+        assert_eq!(
+            transformer
+                .transform("@todo(owner=alice, prio=high) Handle overflows.\n@deprecated(since=2.0) Use `other`.\n@test() Untested.")
+                .unwrap(),
+            "> **Todo** (owner: alice, prio: high): Handle overflows.\n> **Deprecated** (since: 2.0) Use `other`.\n> **Test:** Untested."
+        );
+
+        assert_eq!(
+            transformer
+                .xrefs("@brief Example.\n@bug(owner=bob) Leaks @c memory.\n@param a An argument.")
+                .unwrap(),
+            vec![crate::Xref {
+                kind: "bug".into(),
+                attributes: vec![("owner".into(), "bob".into())],
+                text: "Leaks memory.".into(),
+            }]
+        );
+
+        assert_eq!(
+            transformer
+                .transform("@todo(owner=alice Fix this\n@bug(a=b)Crash.\n@param a b")
+                .unwrap(),
+            "> **Todo:** (owner=alice Fix this\n> **Bug** (a: b): Crash.\n# Arguments\n\n* `a` - b"
+        );
+
+        assert_eq!(
+            transformer
+                .xrefs("@todo Fix.\n\nUnrelated paragraph.")
+                .unwrap()[0]
+                .text,
+            "Fix."
+        );
+    }
+
+    #[test]
+    fn sections() {
+        test_rustdoc!(
+            "@section intro Introduction\nText.\n@subsection usage Usage\n@subsubsection threads Threads\n@paragraph caveats Caveats",
+            "# Introduction\nText.\n## Usage\n### Threads\n#### Caveats"
+        );
+    }
+
+    #[test]
+    fn clamps_headings() {
+        let transformer = Transformer::new().max_heading_depth(2);
+        assert_eq!(
+            transformer
+                .transform("@section intro Introduction\n@subsection usage Usage\n@subsubsection threads Threads\n### Markdown ###\n<h4>HTML</h4>\n<h2>Shallow</h2>\n```c\n### define\n```")
+                .unwrap(),
+            "# Introduction\n## Usage\n**Threads**\n**Markdown**\n**HTML**\n<h2>Shallow</h2>\n```c\n### define\n```"
+        );
+        assert_eq!(
+            transformer
+                .transform("Use `<h3>` tag.\n<h3 id=\"x\">T</h3> and <h4>open\n<h5 class=\"a\">`</h5>`</h5>")
+                .unwrap(),
+            "Use `<h3>` tag.\n**T** and <h4>open\n**`</h5>`**"
+        );
+    }
+
+    #[test]
+    fn command_overrides() {
+        let transformer = Transformer::new()
+            .on_command("param", |command, renderer| {
+                let direction = match command.meta.as_slice() {
+                    [] => String::new(),
+                    meta => format!(" [{}]", meta.join(", ")),
+                };
+                let heading = if renderer.default_output().starts_with("# Arguments") {
+                    "# Parameters\n\n"
+                } else {
+                    ""
+                };
+                format!("{heading}- `{}`{direction}:", command.params[0])
+            })
+            .on_command("since", |_, renderer| {
+                renderer.render("@b Since:").unwrap() + " "
+            });
+
+        assert_eq!(
+            transformer
+                .transform("@param[in] a First.\n@param b Second.\n@since 1.0\n@note Untouched.")
+                .unwrap(),
+            "# Parameters\n\n- `a` [in]: First.\n- `b`: Second.\n**Since:** 1.0\n> **Note:** Untouched."
+        );
+
+        // Rendering the command being overridden uses its default rendering
+        let transformer = Transformer::new().on_command("b", |command, renderer| {
+            renderer
+                .render(&format!("@b {}", command.params[0]))
+                .unwrap()
+                + "!"
+        });
+        assert_eq!(transformer.transform("@b Bold").unwrap(), "**Bold**!");
+
+        // Trimmed whitespace is replaced by the separator of the built-in rendering
+        let transformer = Transformer::new()
+            .argument_whitespace(ArgumentWhitespace::Trim)
+            .on_command("retval", |command, _| {
+                format!("- `{}`: ", command.params[0])
+            })
+            .on_command("throws", |_, renderer| {
+                renderer.default_output().replace('*', "-")
+            });
+        assert_eq!(
+            transformer
+                .transform("@section intro  Introduction\n@param a  The a.\n@retval 0  Success.\n@throws E\tOn error.")
+                .unwrap(),
+            "# Introduction\n# Arguments\n\n* `a` - The a.\n- `0`: Success.\n# Throws\n\n- [`E`] - On error."
+        );
+    }
+
+    #[test]
+    fn braces_in_text() {
+        test_rustdoc!(
+            "{ .x = 1, .y = { 2, 3 } } is the default.\n@note Use `printf(\"{%d}\", v)`.",
+            "{ .x = 1, .y = { 2, 3 } } is the default.\n> **Note:** Use `printf(\"{%d}\", v)`."
+        );
+
+        test_rustdoc!(
+            "@brief Initializes @c a with {0}.\n@{\n* @brief Grouped: {}\n@}",
+            "Initializes `a` with {0}.\n#  Grouped: {}\n"
+        );
+
+        test_rustdoc!("value @ {x} end", "value @ {x} end");
+    }
+
+    #[test]
+    fn examples() {
+        test_rustdoc!(
+            "@brief Opens a file.\n@example open.c Opening a file.\n@example open_twice.cpp Opening it twice.",
+            "Opens a file.\n# Examples\n\n* `open.c` - Opening a file.\n* `open_twice.cpp` - Opening it twice."
+        );
+        test_rustdoc!("Opens a file.\n@example\n", "Opens a file.\n");
+
+        let transformer = Transformer::new().example_resolver(|file| match file {
+            "open.c" => Some(String::from("int main() {\n    open(\"a\");\n}\n")),
+            "doc.md" => Some(String::from("```c\nopen(\"a\");\n```")),
+            _ => None,
+        });
+        assert_eq!(
+            transformer
+                .transform("@example open.c Opening a file.\n@example missing.c Missing.\n@example doc.md Docs.")
+                .unwrap(),
+            "# Examples\n\n* `open.c` - Opening a file.\n\n```c\nint main() {\n    open(\"a\");\n}\n```\n* `missing.c` - Missing.\n* `doc.md` - Docs.\n\n````text\n```c\nopen(\"a\");\n```\n````"
+        );
+        assert_eq!(
+            transformer
+                .examples("@brief Opens a file.\n@example open.c\n@example missing.c Missing.")
+                .unwrap(),
+            vec![String::from("open.c"), String::from("missing.c")]
+        );
+    }
+
+    #[test]
+    fn aggregates_see_also() {
+        let input =
+            "@brief Frees @ref buffer safely.\n@sa free\n@see alloc\n@sa free\n@sa buffer\n@see realloc";
+        test_rustdoc!(
+            "@brief Frees @ref buffer safely.\n@sa free",
+            "Frees [`buffer`] safely.\n[`free`]"
+        );
+        test_rustdoc!("See @ref\n", "See ");
+
+        assert_eq!(
+            Transformer::new()
+                .aggregate_see_also(true)
+                .transform(input)
+                .unwrap(),
+            "Frees [`buffer`] safely.\n\n# See also\n\n* [`free`]\n* [`alloc`]\n* [`realloc`]"
+        );
+
+        assert_eq!(
+            Transformer::new()
+                .aggregate_see_also(true)
+                .see_also_order(|a, b| a.cmp(b))
+                .transform("@sa free\n@see alloc\n@sa free")
+                .unwrap(),
+            "# See also\n\n* [`alloc`]\n* [`free`]"
+        );
+    }
+
+    #[test]
+    fn internal_block() {
+        test_rustdoc!(
+            "Public.\n@internal Private.\n\n@param secret Private too.\n@endinternal\nPublic again.",
+            "Public.\nPublic again."
+        );
+    }
+
+    #[test]
+    fn internal_paragraph() {
+        test_rustdoc!(
+            "Public.\n@internal Private @b and hidden.\n\nPublic paragraph.\n@internal Private again.\n@param example Public argument.",
+            "Public.\n\nPublic paragraph.\n# Arguments\n\n* `example` - Public argument."
+        );
+
+        test_rustdoc!("Text @internal hidden.\n\nNext.", "Text \n\nNext.");
+        test_rustdoc!("@internal Secret.\n\nPublic.", "Public.");
+    }
+
+    #[test]
+    fn internal_references() {
+        assert_eq!(
+            Transformer::new()
+                .aggregate_see_also(true)
+                .transform("Public.\n@internal\n@sa secret_impl\n@endinternal\n@sa public_fn")
+                .unwrap(),
+            "Public.\n\n# See also\n\n* [`public_fn`]"
+        );
+
+        let transformer = Transformer::new();
+        assert_eq!(
+            transformer
+                .examples("@example a.c\n@internal\n@example b.c\n@endinternal\n@example c.c")
+                .unwrap(),
+            vec![String::from("a.c"), String::from("c.c")]
+        );
+        let xrefs = transformer
+            .xrefs("@todo Public.\n@internal\n@bug Hidden.\n@endinternal\n@test Public.")
+            .unwrap();
+        assert_eq!(
+            xrefs.iter().map(|v| v.kind.as_str()).collect::<Vec<_>>(),
+            vec!["todo", "test"]
+        );
+    }
+
+    #[test]
+    fn autobrief() {
+        let transformer = Transformer::new().autobrief(true);
+        let transform = |v| transformer.transform(v).unwrap();
+
+        assert_eq!(
+            transform(
+                "Frees the buffer (i.e. the memory) of @c v in version 1.5. Don't use it.\n\nMore."
+            ),
+            "Frees the buffer (i.e. the memory) of `v` in version 1.5.\n\nDon't use it.\n\nMore."
+        );
+        assert_eq!(
+            transform("Calls `foo(). bar` or ``a. `b` c`` first. Then returns."),
+            "Calls `foo(). bar` or ``a. `b` c`` first.\n\nThen returns."
+        );
+        assert_eq!(
+            transform("释放缓冲区。之后不要使用它！"),
+            "释放缓冲区。\n\n之后不要使用它！"
+        );
+        assert_eq!(
+            transform("Only one sentence.\n\nSecond paragraph. Not split."),
+            "Only one sentence.\n\nSecond paragraph. Not split."
+        );
+        assert_eq!(
+            transform("Frees it. Never fails.\n@param a The a.\n\nNot split. Here."),
+            "Frees it.\n\nNever fails.\n# Arguments\n\n* `a` - The a.\n\nNot split. Here."
+        );
+        assert_eq!(
+            transform("Frees it.\n@param a The a. Not split."),
+            "Frees it.\n# Arguments\n\n* `a` - The a. Not split."
+        );
+        assert_eq!(
+            transform("@brief Explicit. Split too."),
+            "Explicit.\n\nSplit too."
+        );
+        assert_eq!(
+            transform("@brief Frees it.\n@ref buf"),
+            "Frees it.\n\n[`buf`]"
+        );
+        assert_eq!(
+            transform("@brief Explicit.\n@param a The a."),
+            "Explicit.\n\n# Arguments\n\n* `a` - The a."
+        );
+
+        let transformer = transformer.sentence_splitter(|v| v.find(';').map(|end| end + 1));
+        assert_eq!(
+            transformer.transform("Frees it; never fails.").unwrap(),
+            "Frees it;\n\nnever fails."
+        );
+
+        for end in [1, 100] {
+            let transformer = Transformer::new()
+                .autobrief(true)
+                .sentence_splitter(move |_| Some(end));
+            assert_eq!(transformer.transform("é is. x").unwrap(), "é is. x");
+        }
+    }
+
+    #[test]
+    fn markdown_passthrough() {
+        let markdown = "Flags, see `\\n` or mail me@example.com:\n\n| Flag   | Value |\n|--------|------:|\n| `READ` | `0x1` |\n\n```c\n@brief not a command;\nint  a = {0};\n```";
+        let result = rustdoc(markdown.into()).unwrap();
+        assert_eq!(result, markdown);
+        assert_eq!(rustdoc(result.clone()).unwrap(), result);
+
+        assert_eq!(
+            Transformer::new()
+                .max_heading_depth(1)
+                .transform("Intro.\n\n### Deep heading")
+                .unwrap(),
+            "Intro.\n\n**Deep heading**"
+        );
+        assert_eq!(
+            Transformer::new()
+                .autobrief(true)
+                .transform("First sentence. Second.\n\n## Notes")
+                .unwrap(),
+            "First sentence.\n\nSecond.\n\n## Notes"
+        );
+    }
+
+    #[test]
+    fn idempotence() {
+        let inputs = [
+            "@brief Initializes @c a with {0}.\n@{\n* @brief Grouped: {}\n@}",
+            "@param[in,out] example This insane thing.\n@returns Great values!\n@retval example1 Is this an example?",
+            "@throw std::io::bonk This is thrown when INSANE things happen.\n@sa random_thing @see random_thing_2",
+            "@deprecated This function is deprecated!\n@note  Spaced  out.\n@par Paragraph\nText.",
+            "@brief This function is insane!\n@details This is an insane function.",
+            "Plain   text, without commands.",
+            "@pre  x > 0\n@post y",
+            "value @ {x} end",
+            "@brief Explicit. Not split.",
+            "@brief Frees it.\n@ref buf",
+            "@brief Frees it.\n@param a The a. More.\n@return ok",
+            "\n @pre x > 0 @c word",
+            "\n\n @c word",
+        ];
+        let transformers = [
+            Transformer::new(),
+            Transformer::new()
+                .autobrief(true)
+                .aggregate_see_also(true)
+                .max_heading_depth(2)
+                .argument_whitespace(crate::ArgumentWhitespace::Preserve),
+        ];
+
+        for transformer in transformers {
+            for input in inputs {
+                let once = transformer.transform(input).unwrap();
+                assert_eq!(transformer.transform(&once).unwrap(), once);
+            }
+        }
+    }
+
     #[test]
     fn can_parse_example() {
         let example = include_str!("../tests/assets/example-bindgen.rs");
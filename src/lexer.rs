@@ -1,16 +1,25 @@
+use std::ops::Range;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum LexItem {
     At(String),
     Paren(char),
     Word(String),
     Space,
+    /// A run of whitespace other than a single space, like `"  "` or `"\t"`.
+    Spaces(String),
     NewLine,
 }
 
-pub(crate) fn lex(input: String) -> Vec<LexItem> {
+/// Lexes the input, along with the byte range of every item in the input.
+pub(crate) fn lex(input: &str) -> (Vec<LexItem>, Vec<Range<usize>>) {
     let mut result = vec![];
+    let mut spans: Vec<Range<usize>> = vec![];
+
+    for (index, c) in input.char_indices() {
+        let span = index..index + c.len_utf8();
+        let len = result.len();
 
-    for c in input.chars() {
         match c {
             '@' => {
                 result.push(LexItem::At(c.into()));
@@ -31,13 +40,17 @@ pub(crate) fn lex(input: String) -> Vec<LexItem> {
                     result.push(LexItem::At(c.into()));
                 }
             }
-            '{' | '}' => {
+            // Only `@{`/`@}` (or `\{`/`\}`) are group markers, any other brace is just text
+            '{' | '}' if matches!(result.last(), Some(LexItem::At(_))) => {
                 result.push(LexItem::Paren(c));
             }
-            ' ' => {
+            ' ' | '\t' => {
                 if let Some(v) = result.last_mut() {
-                    if !matches!(v, LexItem::Space) {
-                        result.push(LexItem::Space);
+                    match v {
+                        LexItem::Space => *v = LexItem::Spaces(format!(" {c}")),
+                        LexItem::Spaces(v) => v.push(c),
+                        _ if c == ' ' => result.push(LexItem::Space),
+                        _ => result.push(LexItem::Spaces(c.into())),
                     }
                 }
             }
@@ -55,15 +68,39 @@ pub(crate) fn lex(input: String) -> Vec<LexItem> {
                 }
             }
         }
+
+        if result.len() > len {
+            spans.push(span);
+        } else if let Some(last) = spans.last_mut() {
+            last.end = span.end;
+        }
     }
 
-    result
+    (result, spans)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn lex(input: String) -> Vec<LexItem> {
+        super::lex(&input).0
+    }
+
+    #[test]
+    fn spans() {
+        let input = "@param  é\\\\x";
+        let (items, spans) = super::lex(input);
+        assert_eq!(items.len(), spans.len());
+        assert_eq!(
+            spans
+                .into_iter()
+                .map(|span| &input[span])
+                .collect::<Vec<_>>(),
+            vec!["@", "param", "  ", "é", "\\\\", "x"]
+        );
+    }
+
     #[test]
     fn basic_notation() {
         let result = lex("@name Memory Management".into());
@@ -129,4 +166,46 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn space_runs() {
+        let result = lex("@param  var    Description\tText \t Aligned".into());
+        assert_eq!(
+            result,
+            vec![
+                LexItem::At("@".into()),
+                LexItem::Word("param".into()),
+                LexItem::Spaces("  ".into()),
+                LexItem::Word("var".into()),
+                LexItem::Spaces("    ".into()),
+                LexItem::Word("Description".into()),
+                LexItem::Spaces("\t".into()),
+                LexItem::Word("Text".into()),
+                LexItem::Spaces(" \t ".into()),
+                LexItem::Word("Aligned".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn braces_in_text() {
+        let result = lex("struct a = {1, {2}};\n@{".into());
+        assert_eq!(
+            result,
+            vec![
+                LexItem::Word("struct".into()),
+                LexItem::Space,
+                LexItem::Word("a".into()),
+                LexItem::Space,
+                LexItem::Word("=".into()),
+                LexItem::Space,
+                LexItem::Word("{1,".into()),
+                LexItem::Space,
+                LexItem::Word("{2}};".into()),
+                LexItem::NewLine,
+                LexItem::At("@".into()),
+                LexItem::Paren('{'),
+            ]
+        );
+    }
 }
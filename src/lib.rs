@@ -28,10 +28,47 @@
 //! }
 //! ```
 
+mod autobrief;
+mod dot;
 mod emojis;
 pub mod generator;
 mod lexer;
+mod markdown;
 mod parser;
+#[cfg(feature = "async")]
+pub mod stream;
+mod transformer;
+
+pub use autobrief::first_sentence_end;
+pub use transformer::{ArgumentWhitespace, Command, Renderer, Transformer, Xref};
+
+/// Returns whether the comment appears to be converted to Rustdoc already.
+///
+/// This is a heuristic: the comment must not contain any Doxygen command, and must contain
+/// constructs generated by the transformation, like its `# Arguments` sections or intra-doc links.
+/// [`transform`] returns such comments unchanged, so it can safely run again over converted
+/// comments.
+///
+/// Comments which are only partially converted still contain commands, so they are converted
+/// again. The sections of the converted part aren't merged with the ones generated for the
+/// commands, so a `# Arguments` section followed by a `@param` results in two `# Arguments`
+/// sections.
+///
+/// # Examples
+///
+/// ```
+/// use doxygen_rs::{appears_converted, transform};
+///
+/// let doxygen = "@param example An example\n@sa other";
+/// assert!(!appears_converted(doxygen));
+///
+/// let rustdoc = transform(doxygen);
+/// assert!(appears_converted(&rustdoc));
+/// assert_eq!(transform(&rustdoc), rustdoc);
+/// ```
+pub fn appears_converted(value: &str) -> bool {
+    !markdown::has_commands(value) && markdown::is_rustdoc(value)
+}
 
 /// This function transforms the Doxygen of a single element (function, struct, etc.)
 ///
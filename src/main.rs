@@ -0,0 +1,64 @@
+//! Reads a Doxygen comment from the standard input and writes its transformation to the standard
+//! output.
+//!
+//! ```text
+//! doxygen-rs [--emit rustdoc|ast-dot]
+//! ```
+
+use doxygen_rs::Transformer;
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: doxygen-rs [--emit rustdoc|ast-dot] < comment.txt";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut emit = String::from("rustdoc");
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--emit" => match args.next() {
+                Some(v) => emit = v,
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                eprintln!("unexpected argument `{arg}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut input = String::new();
+    if let Err(err) = std::io::stdin().read_to_string(&mut input) {
+        eprintln!("failed to read the standard input: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    let transformer = Transformer::new();
+    let result = match emit.as_str() {
+        "rustdoc" => transformer.transform(&input),
+        "ast-dot" => transformer.ast_dot(&input),
+        _ => {
+            eprintln!("unknown output `{emit}`\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
+        Ok(v) => {
+            print!("{v}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("failed to transform the comment: {err:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,55 @@
+/// Returns whether `input` contains a Doxygen command, like `@brief` or `\{`.
+///
+/// Only `@` and `\` at the start of a word count, so e-mail addresses, escapes like `\*` and
+/// content of code blocks don't.
+pub(crate) fn has_commands(input: &str) -> bool {
+    let mut in_code_block = false;
+
+    input.lines().any(|line| {
+        if is_fence(line) {
+            in_code_block = !in_code_block;
+            return false;
+        }
+
+        !in_code_block
+            && line.char_indices().any(|(index, c)| {
+                let at_word_start = line[..index]
+                    .chars()
+                    .next_back()
+                    .is_none_or(|v| v.is_whitespace() || v == '*');
+                let next = line[index + c.len_utf8()..].chars().next();
+                (c == '@' || c == '\\')
+                    && at_word_start
+                    && next.is_some_and(|v| v.is_ascii_alphabetic() || v == '{' || v == '}')
+            })
+    })
+}
+
+/// Returns whether `input` contains Markdown which the transformation would break, like code
+/// blocks or tables.
+pub(crate) fn is_rich_markdown(input: &str) -> bool {
+    input.lines().any(|line| {
+        let line = line.trim();
+        is_fence(line)
+            || (line.contains('|')
+                && line.contains("---")
+                && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')))
+    })
+}
+
+/// Returns whether `input` contains constructs the transformation generates, like its section
+/// headings, argument lists, links and notes.
+pub(crate) fn is_rustdoc(input: &str) -> bool {
+    input.lines().any(|line| {
+        let line = line.trim_start();
+        (line.starts_with('#') && line.trim_start_matches('#').starts_with(' '))
+            || line.starts_with("> **")
+            || (line.starts_with("* `") && line.contains("` -"))
+            || line.contains("[`")
+    })
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
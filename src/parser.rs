@@ -1,4 +1,6 @@
 use crate::lexer::{lex, LexItem};
+use crate::{ArgumentWhitespace, Transformer};
+use std::ops::Range;
 
 const OPEN_PAREN: char = '{';
 const CLOSED_PAREN: char = '}';
@@ -24,19 +26,104 @@ pub(crate) enum GrammarItem {
     GroupEnd,
 }
 
-pub(crate) fn parse(input: String) -> Result<Vec<GrammarItem>, ParseError> {
-    let mut lexed = lex(input);
+/// Commands which can carry attributes, see [`Transformer::xref_attributes`].
+pub(crate) const XREF_TAGS: [&str; 4] = ["todo", "bug", "test", "deprecated"];
+
+pub(crate) fn parse(
+    input: String,
+    transformer: &Transformer,
+) -> Result<Vec<GrammarItem>, ParseError> {
+    Ok(parse_spanned(&input, transformer)?
+        .into_iter()
+        .map(|(item, _)| item)
+        .collect())
+}
+
+/// Parses the input, along with the byte range in the input every item originates from.
+pub(crate) fn parse_spanned(
+    input: &str,
+    transformer: &Transformer,
+) -> Result<Vec<(GrammarItem, Range<usize>)>, ParseError> {
+    let (mut lexed, mut spans) = lex(input);
+    if let Some((open, close)) = transformer.xref_attributes {
+        (lexed, spans) = merge_xref_attributes(lexed, spans, open, close);
+    }
     lexed.extend_from_slice(&[LexItem::Space, LexItem::Space, LexItem::Space]);
-    parse_items(lexed)
+    parse_items(lexed, &spans, transformer)
+}
+
+/// Merges the words of attributes spanning multiple words (like `@todo(owner=alice, prio=high)`)
+/// into the word of the command. Attributes which aren't closed on the same line are left alone.
+fn merge_xref_attributes(
+    input: Vec<LexItem>,
+    spans: Vec<Range<usize>>,
+    open: char,
+    close: char,
+) -> (Vec<LexItem>, Vec<Range<usize>>) {
+    let mut result: Vec<LexItem> = Vec::with_capacity(input.len());
+    let mut result_spans: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    let mut index = 0;
+
+    while let Some(item) = input.get(index) {
+        index += 1;
+
+        if let (LexItem::Word(v), Some(LexItem::At(_))) = (item, result.last()) {
+            let unclosed = v.split_once(open).is_some_and(|(tag, attributes)| {
+                XREF_TAGS.contains(&tag) && !attributes.contains(close)
+            });
+            let end = input[index..]
+                .iter()
+                .position(|v| match v {
+                    LexItem::Word(v) => v.contains(close),
+                    LexItem::Space | LexItem::Spaces(_) => false,
+                    _ => true,
+                })
+                .filter(|&offset| matches!(input[index + offset], LexItem::Word(_)));
+
+            if let (true, Some(offset)) = (unclosed, end) {
+                let mut word = v.clone();
+                for v in &input[index..=index + offset] {
+                    match v {
+                        LexItem::Word(v) => word += v,
+                        _ => word += " ",
+                    }
+                }
+
+                result.push(LexItem::Word(word));
+                result_spans.push(spans[index - 1].start..spans[index + offset].end);
+                index += offset + 1;
+                continue;
+            }
+        }
+
+        result.push(item.clone());
+        result_spans.push(spans[index - 1].clone());
+    }
+
+    (result, result_spans)
 }
 
-fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
+fn parse_items(
+    input: Vec<LexItem>,
+    lex_spans: &[Range<usize>],
+    transformer: &Transformer,
+) -> Result<Vec<(GrammarItem, Range<usize>)>, ParseError> {
     let mut grammar_items = vec![];
+    let mut spans: Vec<Range<usize>> = vec![];
+    let mut last_notation = 0;
     let mut param_iter_skip_count = 0;
+    let mut after_params = false;
+
+    for (item, span) in input.windows(4).zip(lex_spans) {
+        let items_len = grammar_items.len();
+        let text_len = match grammar_items.last() {
+            Some(GrammarItem::Text(v)) => Some(v.len()),
+            _ => None,
+        };
+        let mut skipped = false;
 
-    for item in input.windows(4) {
         match &item[0] {
-            LexItem::At(_) => {
+            LexItem::At(at) => {
                 if let Some(next) = item.get(1) {
                     match next {
                         LexItem::Paren(v) => match *v {
@@ -53,6 +140,7 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
                             let mut meta = vec![];
                             let params;
                             let content;
+                            let mut remainder = String::new();
 
                             if v.starts_with("param") {
                                 if let Some(value) = v.split('[').nth(1) {
@@ -77,17 +165,44 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
                                 };
 
                                 content = "param"
+                            } else if let Some((tag, attributes, rest)) =
+                                transformer.xref_attributes.and_then(|(open, close)| {
+                                    let (tag, rest) = v.split_once(open)?;
+                                    if !XREF_TAGS.contains(&tag) {
+                                        return None;
+                                    }
+
+                                    // Without a closing delimiter, there are no attributes
+                                    Some(match rest.split_once(close) {
+                                        Some((attributes, rest)) => (tag, attributes, rest.into()),
+                                        None => (tag, "", format!("{open}{rest}")),
+                                    })
+                                })
+                            {
+                                remainder = rest;
+                                meta.extend(
+                                    attributes
+                                        .split(',')
+                                        .map(str::trim)
+                                        .filter(|v| !v.is_empty())
+                                        .map(String::from),
+                                );
+                                params = vec![];
+                                content = tag;
                             } else {
                                 content = v;
 
                                 params = match v.as_str() {
                                     "a" | "b" | "c" | "p" | "emoji" | "e" | "em" | "def"
                                     | "class" | "category" | "concept" | "enum" | "example"
-                                    | "extends" | "file" | "sa" | "see" | "retval"
-                                    | "exception" | "throw" | "throws" => match item.get(3) {
-                                        Some(LexItem::Word(v)) => vec![v.into()],
-                                        _ => vec![],
-                                    },
+                                    | "extends" | "file" | "sa" | "see" | "ref" | "retval"
+                                    | "exception" | "throw" | "throws" | "section"
+                                    | "subsection" | "subsubsection" | "paragraph" => {
+                                        match item.get(3) {
+                                            Some(LexItem::Word(v)) => vec![v.into()],
+                                            _ => vec![],
+                                        }
+                                    }
                                     _ => vec![],
                                 };
                             }
@@ -97,12 +212,26 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
                             } else {
                                 param_iter_skip_count = 2;
                             }
+                            after_params = !params.is_empty();
 
+                            last_notation = grammar_items.len();
                             grammar_items.push(GrammarItem::Notation {
                                 meta,
                                 params,
                                 tag: content.into(),
                             });
+
+                            // Text directly following the attributes, like `Fix` in `@todo(a=b)Fix`
+                            if !remainder.is_empty() {
+                                grammar_items.push(GrammarItem::Text(remainder));
+                            }
+                        }
+                        // A stray `@` or `\` isn't a command, like in `a @ b`
+                        LexItem::Space | LexItem::Spaces(_) | LexItem::NewLine => {
+                            match grammar_items.last_mut() {
+                                Some(GrammarItem::Text(text)) => *text += at,
+                                _ => grammar_items.push(GrammarItem::Text(at.into())),
+                            }
                         }
                         _ => {}
                     }
@@ -111,22 +240,34 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
             LexItem::Word(v) => {
                 if param_iter_skip_count > 0 {
                     param_iter_skip_count -= 1;
-                    continue;
-                }
-
-                if let Some(prev) = grammar_items.last_mut() {
-                    match prev {
-                        GrammarItem::Text(text) => *text += v,
-                        _ => grammar_items.push(GrammarItem::Text(v.into())),
-                    }
+                    skipped = true;
                 } else {
-                    grammar_items.push(GrammarItem::Text(v.into()));
+                    after_params = false;
+                    if let Some(prev) = grammar_items.last_mut() {
+                        match prev {
+                            GrammarItem::Text(text) => *text += v,
+                            _ => grammar_items.push(GrammarItem::Text(v.into())),
+                        }
+                    } else {
+                        grammar_items.push(GrammarItem::Text(v.into()));
+                    }
                 }
             }
-            LexItem::Space => {
+            LexItem::Space | LexItem::Spaces(_) => {
+                // The whitespace between the arguments of a notation and its text
+                let leading = after_params && param_iter_skip_count == 0;
+                let space = match (&item[0], leading, transformer.argument_whitespace) {
+                    (_, true, ArgumentWhitespace::Trim) => String::new(),
+                    (LexItem::Spaces(v), true, ArgumentWhitespace::Preserve) => v.clone(),
+                    _ => String::from(" "),
+                };
+                if leading {
+                    after_params = false;
+                }
+
                 if let Some(prev) = grammar_items.last_mut() {
                     match prev {
-                        GrammarItem::Text(text) => *text += " ",
+                        GrammarItem::Text(text) => *text += &space,
                         _ => grammar_items.push(GrammarItem::Text("".into())),
                     }
                 } else {
@@ -134,6 +275,7 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
                 }
             }
             LexItem::NewLine => {
+                after_params = false;
                 if let Some(GrammarItem::Text(text)) = grammar_items.last_mut() {
                     *text += "\n"
                 }
@@ -144,9 +286,29 @@ fn parse_items(input: Vec<LexItem>) -> Result<Vec<GrammarItem>, ParseError> {
                 }
             }
         }
+
+        // Notations span their arguments, texts span from their first character on
+        if skipped {
+            spans[last_notation].end = span.end;
+        } else if matches!(item[0], LexItem::Paren(_)) && grammar_items.len() == items_len {
+            if let Some(last) = spans.last_mut() {
+                last.end = span.end;
+            }
+        } else if grammar_items.len() > items_len {
+            spans.resize(grammar_items.len(), span.clone());
+        } else if let (Some(GrammarItem::Text(v)), Some(last)) =
+            (grammar_items.last(), spans.last_mut())
+        {
+            if text_len == Some(0) {
+                last.start = span.start;
+            }
+            if text_len != Some(v.len()) {
+                last.end = span.end;
+            }
+        }
     }
 
-    Ok(grammar_items)
+    Ok(grammar_items.into_iter().zip(spans).collect())
 }
 
 #[cfg(test)]
@@ -155,7 +317,7 @@ mod test {
 
     #[test]
     pub fn simple_notation() {
-        let result = parse("@name Memory Management".into()).unwrap();
+        let result = parse("@name Memory Management".into(), &Transformer::default()).unwrap();
         assert_eq!(
             result,
             vec![
@@ -171,7 +333,7 @@ mod test {
 
     #[test]
     pub fn paren_in_notation() {
-        let result = parse("@note hoge_t = {a, b, c}".into()).unwrap();
+        let result = parse("@note hoge_t = {a, b, c}".into(), &Transformer::default()).unwrap();
         assert_eq!(
             result,
             vec![
@@ -187,8 +349,11 @@ mod test {
 
     #[test]
     pub fn param() {
-        let result =
-            parse("@param[in] random This is, without a doubt, a random argument.".into()).unwrap();
+        let result = parse(
+            "@param[in] random This is, without a doubt, a random argument.".into(),
+            &Transformer::default(),
+        )
+        .unwrap();
         assert_eq!(
             result,
             vec![
@@ -204,7 +369,11 @@ mod test {
 
     #[test]
     pub fn groups() {
-        let result = parse("@{\n* @name Memory Management\n@}".into()).unwrap();
+        let result = parse(
+            "@{\n* @name Memory Management\n@}".into(),
+            &Transformer::default(),
+        )
+        .unwrap();
         assert_eq!(
             result,
             vec![
@@ -226,6 +395,7 @@ mod test {
         let result = parse(
             "@param[in]           var                                         Example description"
                 .into(),
+            &Transformer::default(),
         )
         .unwrap();
         assert_eq!(
@@ -240,4 +410,35 @@ mod test {
             ]
         )
     }
+
+    #[test]
+    pub fn argument_whitespace() {
+        let input = "@param[in]    var\t\tAligned   description\n@c    word    Text";
+        let texts = |policy| {
+            parse(
+                input.into(),
+                &Transformer::new().argument_whitespace(policy),
+            )
+            .unwrap()
+            .into_iter()
+            .filter_map(|item| match item {
+                GrammarItem::Text(v) => Some(v),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            texts(ArgumentWhitespace::Collapse),
+            vec![" Aligned description\n", " Text"]
+        );
+        assert_eq!(
+            texts(ArgumentWhitespace::Preserve),
+            vec!["\t\tAligned description\n", "    Text"]
+        );
+        assert_eq!(
+            texts(ArgumentWhitespace::Trim),
+            vec!["Aligned description\n", "Text"]
+        );
+    }
 }
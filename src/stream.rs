@@ -0,0 +1,100 @@
+//! Asynchronous, runtime-agnostic conversion of many comments at once.
+//!
+//! Available with the `async` feature. The conversion is driven by the consumer of the returned
+//! [`Stream`], so a slow consumer naturally applies back-pressure to the producer of the comments.
+
+use crate::generator;
+use crate::parser::ParseError;
+use crate::Transformer;
+use futures::stream::{Stream, StreamExt};
+
+/// Transforms a [`Stream`] of Doxygen comments into a [`Stream`] of Rustdoc comments.
+///
+/// Each comment is converted only when the returned stream is polled for it, and results are
+/// yielded in the same order as the input.
+///
+/// # Examples
+///
+/// ```
+/// use doxygen_rs::stream::transform_stream;
+/// use futures::{executor::block_on, stream, StreamExt};
+///
+/// let comments = stream::iter(["@brief First", "@brief Second"]);
+/// let converted: Vec<_> = block_on(transform_stream(comments).collect());
+/// assert_eq!(converted[1].as_deref().unwrap(), "Second");
+/// ```
+pub fn transform_stream<S>(comments: S) -> impl Stream<Item = Result<String, ParseError>>
+where
+    S: Stream,
+    S::Item: AsRef<str>,
+{
+    comments.map(|comment| generator::rustdoc(comment.as_ref().into()))
+}
+
+impl Transformer {
+    /// Transforms a [`Stream`] of Doxygen comments into a [`Stream`] of Rustdoc comments with this
+    /// configuration, like [`transform_stream`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    /// use futures::{executor::block_on, stream, StreamExt};
+    ///
+    /// let transformer = Transformer::new().max_heading_depth(0);
+    /// let comments = stream::iter(["@param a The a."]);
+    /// let converted: Vec<_> = block_on(transformer.transform_stream(comments).collect());
+    /// assert_eq!(converted[0].as_deref().unwrap(), "**Arguments**\n\n* `a` - The a.");
+    /// ```
+    pub fn transform_stream<'a, S>(
+        &'a self,
+        comments: S,
+    ) -> impl Stream<Item = Result<String, ParseError>> + 'a
+    where
+        S: Stream + 'a,
+        S::Item: AsRef<str>,
+    {
+        comments.map(move |comment| self.transform(comment.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{executor::block_on, stream};
+
+    #[test]
+    fn keeps_order() {
+        let comments = stream::iter(vec![
+            String::from("@brief First"),
+            String::from("@param example An example"),
+            String::from("@b Bold"),
+        ]);
+        let result: Vec<String> = block_on(transform_stream(comments).collect::<Vec<_>>())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(
+            result,
+            vec![
+                "First",
+                "# Arguments\n\n* `example` - An example",
+                "**Bold**"
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_transformer() {
+        let transformer = Transformer::new().autobrief(true);
+        let comments = stream::iter(["Frees it. Never fails.", "@b Bold"]);
+        let result: Vec<String> =
+            block_on(transformer.transform_stream(comments).collect::<Vec<_>>())
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+        assert_eq!(result, vec!["Frees it.\n\nNever fails.", "**Bold**"]);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::dot;
+use crate::generator;
+use crate::parser::{parse, parse_spanned, GrammarItem, ParseError, XREF_TAGS};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+type RenderCommand = dyn Fn(&Command, &Renderer) -> String + Send + Sync;
+type ResolveExample = dyn Fn(&str) -> Option<String> + Send + Sync;
+type OrderSeeAlso = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+type SplitSentence = dyn Fn(&str) -> Option<usize> + Send + Sync;
+
+/// A configurable Doxygen to Rustdoc transformation.
+///
+/// [`transform`](crate::transform) uses the default configuration, use this type when you need to
+/// opt into additional behavior.
+///
+/// # Examples
+///
+/// ```
+/// use doxygen_rs::Transformer;
+///
+/// let transformer = Transformer::new().link_out_params(true);
+/// let rustdoc = transformer
+///     .transform("@param[out] handle The handle\n@return Zero, the handle is stored in *handle")
+///     .unwrap();
+/// assert!(rustdoc.ends_with("the handle is stored in [`*handle`](#arguments)"));
+/// ```
+#[derive(Clone, Default)]
+pub struct Transformer {
+    pub(crate) link_out_params: bool,
+    pub(crate) xref_attributes: Option<(char, char)>,
+    pub(crate) max_heading_depth: Option<usize>,
+    pub(crate) command_overrides: HashMap<String, Arc<RenderCommand>>,
+    pub(crate) example_resolver: Option<Arc<ResolveExample>>,
+    pub(crate) aggregate_see_also: bool,
+    pub(crate) see_also_order: Option<Arc<OrderSeeAlso>>,
+    pub(crate) autobrief: bool,
+    pub(crate) sentence_splitter: Option<Arc<SplitSentence>>,
+    pub(crate) argument_whitespace: ArgumentWhitespace,
+}
+
+impl fmt::Debug for Transformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transformer")
+            .field("link_out_params", &self.link_out_params)
+            .field("xref_attributes", &self.xref_attributes)
+            .field("max_heading_depth", &self.max_heading_depth)
+            .field(
+                "command_overrides",
+                &self.command_overrides.keys().collect::<Vec<_>>(),
+            )
+            .field("example_resolver", &self.example_resolver.is_some())
+            .field("aggregate_see_also", &self.aggregate_see_also)
+            .field("see_also_order", &self.see_also_order.is_some())
+            .field("autobrief", &self.autobrief)
+            .field("argument_whitespace", &self.argument_whitespace)
+            .field("sentence_splitter", &self.sentence_splitter.is_some())
+            .finish()
+    }
+}
+
+/// How the whitespace between the arguments of a command and its text is handled, see
+/// [`Transformer::argument_whitespace`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ArgumentWhitespace {
+    /// Replaces the whitespace by a single space.
+    #[default]
+    Collapse,
+    /// Keeps the whitespace as is, so column-aligned descriptions stay aligned.
+    Preserve,
+    /// Removes the whitespace, for renderers adding their own spacing (see
+    /// [`Transformer::on_command`]). The built-in rendering of commands ends with a single space
+    /// instead, which is part of [`Renderer::default_output`].
+    Trim,
+}
+
+/// A parsed Doxygen command, passed to the closures registered with [`Transformer::on_command`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Command {
+    /// The name of the command, like `param` for `@param[in] value`.
+    pub tag: String,
+    /// Additional information of the command, like the direction `in` for `@param[in] value`.
+    pub meta: Vec<String>,
+    /// The arguments of the command, like `value` for `@param[in] value`.
+    pub params: Vec<String>,
+}
+
+/// A handle to the rendering of a [`Command`], passed to the closures registered with
+/// [`Transformer::on_command`].
+#[derive(Debug)]
+pub struct Renderer<'a> {
+    pub(crate) transformer: &'a Transformer,
+    pub(crate) tag: &'a str,
+    pub(crate) default_output: &'a str,
+}
+
+impl Renderer<'_> {
+    /// The output the command would have without the override, including any section heading it
+    /// starts (like `# Arguments` for the first `@param`).
+    pub fn default_output(&self) -> &str {
+        self.default_output
+    }
+
+    /// Transforms another piece of Doxygen with the same configuration, except that the command
+    /// being rendered uses its default rendering, so an override can't recurse into itself.
+    ///
+    /// # Errors
+    ///
+    /// This function errors in the same cases as [`Transformer::transform`].
+    pub fn render(&self, value: &str) -> Result<String, ParseError> {
+        let mut transformer = self.transformer.clone();
+        transformer.command_overrides.remove(self.tag);
+        transformer.transform(value)
+    }
+}
+
+/// An occurrence of a cross-reference list command (`@todo`, `@bug`, `@test` or `@deprecated`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Xref {
+    /// The command, like `todo`.
+    pub kind: String,
+    /// The attributes of the command, in order, see [`Transformer::xref_attributes`].
+    ///
+    /// Attributes without a `=` have an empty value.
+    pub attributes: Vec<(String, String)>,
+    /// The plain text following the command, without styling.
+    pub text: String,
+}
+
+impl Transformer {
+    /// Creates a transformer with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Links dereferenced mentions of `[out]` parameters (like `*out`) in `@return` descriptions to
+    /// the `# Arguments` section.
+    ///
+    /// Some SDKs document output pointers as "returns the handle in *out", this connects that text
+    /// with the parameter documentation instead of leaving it as an unrelated duplicate.
+    pub fn link_out_params(mut self, enabled: bool) -> Self {
+        self.link_out_params = enabled;
+        self
+    }
+
+    /// Enables attributes on cross-reference list commands, delimited by `open` and `close`.
+    ///
+    /// With `('(', ')')`, `@todo(owner=alice, prio=high) Fix this` is rendered as
+    /// `> **Todo** (owner: alice, prio: high): Fix this`, and the attributes are available through
+    /// [`Transformer::xrefs`].
+    pub fn xref_attributes(mut self, open: char, close: char) -> Self {
+        self.xref_attributes = Some((open, close));
+        self
+    }
+
+    /// Clamps headings (from `@section` and friends, HTML `<hN>` tags or Markdown `#`) to the
+    /// given depth, rendering deeper headings as bold text instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    ///
+    /// let transformer = Transformer::new().max_heading_depth(2);
+    /// let rustdoc = transformer
+    ///     .transform("@section intro Introduction\n@subsubsection details Details")
+    ///     .unwrap();
+    /// assert_eq!(rustdoc, "# Introduction\n**Details**");
+    /// ```
+    pub fn max_heading_depth(mut self, depth: usize) -> Self {
+        self.max_heading_depth = Some(depth);
+        self
+    }
+
+    /// Overrides how the command `tag` is rendered.
+    ///
+    /// The text following the command is still rendered as usual, only the output of the command
+    /// itself is replaced by the output of `render`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    ///
+    /// let transformer = Transformer::new().on_command("note", |_, _| String::from("> ℹ️ "));
+    /// let rustdoc = transformer.transform("@note Handle with care.").unwrap();
+    /// assert_eq!(rustdoc, "> ℹ️ Handle with care.");
+    /// ```
+    pub fn on_command<F>(mut self, tag: &str, render: F) -> Self
+    where
+        F: Fn(&Command, &Renderer) -> String + Send + Sync + 'static,
+    {
+        self.command_overrides.insert(tag.into(), Arc::new(render));
+        self
+    }
+
+    /// Inlines the files referenced by `@example` under the `# Examples` section, as code blocks
+    /// following their description.
+    ///
+    /// `resolve` receives the file name given to `@example` and returns its content, or [`None`]
+    /// to only list the file name.
+    pub fn example_resolver<F>(mut self, resolve: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.example_resolver = Some(Arc::new(resolve));
+        self
+    }
+
+    /// Collects the targets of `@sa`/`@see` in a `# See also` section at the end, instead of
+    /// linking them in place.
+    ///
+    /// Targets are listed once, in the order they're first seen, and targets already linked in the
+    /// text with `@ref` are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    ///
+    /// let transformer = Transformer::new().aggregate_see_also(true);
+    /// let rustdoc = transformer
+    ///     .transform("Frees the buffer.\n@sa alloc\n@see free\n@sa alloc")
+    ///     .unwrap();
+    /// assert_eq!(rustdoc, "Frees the buffer.\n\n# See also\n\n* [`alloc`]\n* [`free`]");
+    /// ```
+    pub fn aggregate_see_also(mut self, enabled: bool) -> Self {
+        self.aggregate_see_also = enabled;
+        self
+    }
+
+    /// Sorts the targets of the `# See also` section, see [`Transformer::aggregate_see_also`].
+    ///
+    /// The sort is stable, so targets comparing equal keep the order they're first seen in.
+    pub fn see_also_order<F>(mut self, order: F) -> Self
+    where
+        F: Fn(&str, &str) -> Ordering + Send + Sync + 'static,
+    {
+        self.see_also_order = Some(Arc::new(order));
+        self
+    }
+
+    /// Uses the first sentence as the brief description, like Doxygen's `JAVADOC_AUTOBRIEF`, by
+    /// splitting it into a paragraph of its own.
+    ///
+    /// A `@brief` ends with a paragraph break, and its first sentence is split as well: a converted
+    /// comment can't be told apart from one without `@brief`, so this keeps converting it again a
+    /// no-op.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    ///
+    /// let transformer = Transformer::new().autobrief(true);
+    /// let rustdoc = transformer
+    ///     .transform("Frees the buffer, e.g. on exit. It must not be used afterwards.")
+    ///     .unwrap();
+    /// assert_eq!(rustdoc, "Frees the buffer, e.g. on exit.\n\nIt must not be used afterwards.");
+    /// ```
+    pub fn autobrief(mut self, enabled: bool) -> Self {
+        self.autobrief = enabled;
+        self
+    }
+
+    /// Replaces [`first_sentence_end`](crate::first_sentence_end) as the way to find the first
+    /// sentence for [`Transformer::autobrief`], for instance to support other languages.
+    ///
+    /// `split` receives the first paragraph and returns the byte index where its first sentence
+    /// ends, if any. Indices past the end of the paragraph or inside a character are ignored, so
+    /// the paragraph isn't split.
+    pub fn sentence_splitter<F>(mut self, split: F) -> Self
+    where
+        F: Fn(&str) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.sentence_splitter = Some(Arc::new(split));
+        self
+    }
+
+    /// Renders the parsed structure of the Doxygen of a single element as a Graphviz DOT graph,
+    /// for debugging. Every node is labeled with the byte range and lines it originates from.
+    ///
+    /// This is also available from the command line with `doxygen-rs --emit ast-dot`.
+    ///
+    /// # Errors
+    ///
+    /// This function errors in the same cases as [`Transformer::transform`].
+    pub fn ast_dot(&self, value: &str) -> Result<String, ParseError> {
+        Ok(dot::ast_dot(value, &parse_spanned(value, self)?))
+    }
+
+    /// Sets how the whitespace between the arguments of a command and its text is handled, like
+    /// the spaces between `value` and `The value` in `@param value   The value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::{ArgumentWhitespace, Transformer};
+    ///
+    /// let transformer = Transformer::new().argument_whitespace(ArgumentWhitespace::Preserve);
+    /// let rustdoc = transformer
+    ///     .transform("@param a    The first.\n@param bcd  The second.")
+    ///     .unwrap();
+    /// assert_eq!(rustdoc, "# Arguments\n\n* `a` -    The first.\n* `bcd` -  The second.");
+    /// ```
+    pub fn argument_whitespace(mut self, policy: ArgumentWhitespace) -> Self {
+        self.argument_whitespace = policy;
+        self
+    }
+
+    /// Collects the files referenced by `@example` in the Doxygen of a single element, in order,
+    /// leaving out the ones hidden by `@internal`.
+    ///
+    /// This allows associating the element with its examples, for instance to generate `examples/`
+    /// entries for it.
+    ///
+    /// # Errors
+    ///
+    /// This function errors in the same cases as [`Transformer::transform`].
+    pub fn examples(&self, value: &str) -> Result<Vec<String>, ParseError> {
+        Ok(generator::strip_internal(parse(value.into(), self)?)
+            .into_iter()
+            .filter_map(|item| match item {
+                GrammarItem::Notation { params, tag, .. } if tag == "example" => {
+                    params.into_iter().next()
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Collects the cross-reference list commands of the Doxygen of a single element, leaving out
+    /// the ones hidden by `@internal`.
+    ///
+    /// # Errors
+    ///
+    /// This function errors in the same cases as [`Transformer::transform`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doxygen_rs::Transformer;
+    ///
+    /// let transformer = Transformer::new().xref_attributes('(', ')');
+    /// let xrefs = transformer
+    ///     .xrefs("@todo(owner=alice, prio=high) Handle overflows")
+    ///     .unwrap();
+    /// assert_eq!(xrefs[0].kind, "todo");
+    /// assert_eq!(xrefs[0].attributes[0], ("owner".into(), "alice".into()));
+    /// assert_eq!(xrefs[0].text, "Handle overflows");
+    /// ```
+    pub fn xrefs(&self, value: &str) -> Result<Vec<Xref>, ParseError> {
+        let mut result: Vec<Xref> = vec![];
+        let mut in_xref = false;
+
+        for item in generator::strip_internal(parse(value.into(), self)?) {
+            match item {
+                GrammarItem::Notation { meta, tag, .. } if XREF_TAGS.contains(&tag.as_str()) => {
+                    in_xref = true;
+                    result.push(Xref {
+                        kind: tag,
+                        attributes: meta
+                            .iter()
+                            .map(|v| match v.split_once('=') {
+                                Some((key, value)) => (key.trim().into(), value.trim().into()),
+                                None => (v.clone(), String::new()),
+                            })
+                            .collect(),
+                        text: String::new(),
+                    });
+                }
+                GrammarItem::Text(v) if in_xref => {
+                    // The text ends with its paragraph
+                    let (text, end) = match v.split_once("\n\n") {
+                        Some((text, _)) => (text, true),
+                        None => (v.as_str(), false),
+                    };
+                    if let Some(xref) = result.last_mut() {
+                        xref.text += text;
+                    }
+                    in_xref = !end;
+                }
+                GrammarItem::Notation { params, tag, .. }
+                    if in_xref && generator::is_inline(&tag) =>
+                {
+                    if let Some(xref) = result.last_mut() {
+                        xref.text += &params.join(" ");
+                    }
+                }
+                GrammarItem::Text(_) => {}
+                _ => in_xref = false,
+            }
+        }
+
+        for xref in &mut result {
+            xref.text = xref.text.trim().into();
+        }
+
+        Ok(result)
+    }
+
+    /// Transforms the Doxygen of a single element (function, struct, etc.)
+    ///
+    /// # Errors
+    ///
+    /// This function can error if there are missing parts of a given Doxygen annotation (like
+    /// `@param` missing the variable name)
+    pub fn transform(&self, value: &str) -> Result<String, ParseError> {
+        generator::generate(value.into(), self)
+    }
+}